@@ -1,12 +1,112 @@
+// ureq::Error carries its surrounding context (including response bodies) inline,
+// so it's large by design; boxing it would ripple through every call site.
+#![allow(clippy::result_large_err)]
+
 use anyhow::{Context, Result};
 use clap::Parser;
+use log::{error, info, warn, LevelFilter};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
-use std::net::{IpAddr, ToSocketAddrs};
+use std::fs::File;
+use std::net::{IpAddr, Ipv6Addr, ToSocketAddrs};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
 #[derive(Parser)]
 struct Cli {
-    domain: String,
+    /// Domain to update a single A/AAAA record for. Ignored if `--config` is given.
+    domain: Option<String>,
+
+    /// Local network interface to read the public IPv6 address from, instead
+    /// of querying an external service. Useful on SLAAC networks where the
+    /// interface already carries the globally routable prefix.
+    #[arg(long)]
+    ipv6_interface: Option<String>,
+
+    /// Path to a JSON config file describing multiple zones and DNS entries to
+    /// keep in sync. When given, `domain` and `ipv6_interface` are ignored.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Run continuously, polling every INTERVAL seconds instead of exiting
+    /// after a single sync.
+    #[arg(long)]
+    interval: Option<u64>,
+}
+
+/// In-memory form of either a `--config` file or an equivalent single-domain
+/// CLI invocation, so `main` only ever has to deal with one shape.
+#[derive(Deserialize)]
+struct Config {
+    api_key: String,
+    #[serde(default)]
+    ipv6_interface: Option<String>,
+    /// Ordered list of services to query for the public IPv4 address, tried
+    /// in turn until one returns a parseable address.
+    #[serde(default = "default_ipv4_providers")]
+    ipv4_providers: Vec<String>,
+    /// Same as `ipv4_providers`, for the public IPv6 address. Unused when
+    /// `ipv6_interface` is set.
+    #[serde(default = "default_ipv6_providers")]
+    ipv6_providers: Vec<String>,
+    zones: Vec<ZoneConfig>,
+}
+
+fn default_ipv4_providers() -> Vec<String> {
+    vec![
+        "https://api.ipify.org".to_string(),
+        "https://am.i.mullvad.net/ip".to_string(),
+    ]
+}
+
+fn default_ipv6_providers() -> Vec<String> {
+    vec![
+        "https://api6.ipify.org".to_string(),
+        "https://am.i.mullvad.net/ip".to_string(),
+    ]
+}
+
+#[derive(Deserialize)]
+struct ZoneConfig {
+    name: String,
+    id: String,
+    #[serde(default)]
+    email: String,
+    entries: Vec<EntryConfig>,
+}
+
+#[derive(Deserialize)]
+struct EntryConfig {
+    name: String,
+    #[serde(default)]
+    type4: bool,
+    #[serde(default)]
+    type6: bool,
+}
+
+/// The DNS record types this tool knows how to keep in sync.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum RecordType {
+    A,
+    AAAA,
+}
+
+impl RecordType {
+    fn as_str(self) -> &'static str {
+        match self {
+            RecordType::A => "A",
+            RecordType::AAAA => "AAAA",
+        }
+    }
+}
+
+impl std::fmt::Display for RecordType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 #[derive(Deserialize)]
@@ -30,7 +130,7 @@ struct Zone {
     name: String,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 struct DnsRecord {
     id: String,
     zone_id: String,
@@ -41,61 +141,285 @@ struct DnsRecord {
 }
 
 fn main() -> Result<()> {
+    init_logging();
+
     let args: Cli = Cli::parse();
-    let domain: &str = &args.domain;
-    let current_ip: IpAddr = public_ip().context("Getting current public IPv4 Address")?;
-    let dns_ip: IpAddr = dns_ip(domain).context("Getting IPv4 Address Associated With Domain")?;
+    let interval: Option<u64> = args.interval;
+    let config: Config = build_config(args)?;
 
-    if current_ip == dns_ip {
-        println!("IPv4 Address matches. Exiting.");
+    let mut last_seen: HashMap<RecordType, IpAddr> = HashMap::new();
+
+    loop {
+        if let Err(err) = sync(&config, &mut last_seen) {
+            error!("Sync failed, will retry: {err:#}");
+        }
+
+        match interval {
+            Some(seconds) => thread::sleep(Duration::from_secs(seconds)),
+            None => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets up the `log` facade, writing structured fields to the systemd journal
+/// when stdout is connected to it and falling back to plain stdout/stderr
+/// otherwise. Lets journal-aware deployments query update events, skips, and
+/// API failures by severity instead of scraping plain text.
+fn init_logging() {
+    if systemd_journal_logger::connected_to_journal() {
+        systemd_journal_logger::JournalLog::new()
+            .expect("Connecting to systemd journal")
+            .install()
+            .expect("Installing systemd journal logger");
+    } else {
+        env_logger::Builder::new()
+            .filter_level(LevelFilter::Info)
+            .init();
+    }
+    log::set_max_level(LevelFilter::Info);
+}
+
+/// Runs one full sync pass: fetches the current public addresses and patches
+/// whichever DNS records in `config` are stale.
+///
+/// Skips contacting Cloudflare entirely when the public addresses match
+/// `last_seen`, which `main` carries across daemon ticks.
+fn sync(config: &Config, last_seen: &mut HashMap<RecordType, IpAddr>) -> Result<()> {
+    let ipv4: IpAddr = public_ip(&config.ipv4_providers).context("Getting current public IPv4 Address")?;
+    let ipv6: Option<IpAddr> = match public_ipv6(config.ipv6_interface.as_deref(), &config.ipv6_providers) {
+        Ok(ipv6) => Some(ipv6),
+        Err(err) => {
+            warn!("No public IPv6 Address available, skipping AAAA records: {err:#}");
+            None
+        }
+    };
+
+    let mut current_addresses: HashMap<RecordType, IpAddr> = HashMap::new();
+    current_addresses.insert(RecordType::A, ipv4);
+    if let Some(ipv6) = ipv6 {
+        current_addresses.insert(RecordType::AAAA, ipv6);
+    }
+
+    if *last_seen == current_addresses {
+        info!("Public Addresses unchanged since last check. Skipping Cloudflare sync.");
         return Ok(());
     }
 
-    println!("IPv4 Address does not match. Updating Cloudflare DNS records.");
+    for zone in &config.zones {
+        if zone.email.is_empty() {
+            info!("Syncing zone {}", zone.name);
+        } else {
+            info!("Syncing zone {} ({})", zone.name, zone.email);
+        }
 
-    let api_key: String = env::var("CLOUDFLARE_API_KEY")?;
+        let dns_records: Vec<DnsRecord> = get_dns_records(&config.api_key, &zone.id)?;
+
+        for entry in &zone.entries {
+            let mut wanted: Vec<(RecordType, IpAddr)> = Vec::new();
+            if entry.type4 {
+                wanted.push((RecordType::A, ipv4));
+            }
+            if entry.type6 {
+                if let Some(ipv6) = ipv6 {
+                    wanted.push((RecordType::AAAA, ipv6));
+                }
+            }
+
+            let stale: Vec<(RecordType, IpAddr)> = wanted
+                .into_iter()
+                .filter(
+                    |(record_type, current_ip)| match dns_ip(&entry.name, *record_type) {
+                        Ok(dns_ip) if dns_ip == *current_ip => {
+                            info!("{record_type} Address for {} matches. Skipping.", entry.name);
+                            false
+                        }
+                        _ => true,
+                    },
+                )
+                .collect();
 
+            for (record_type, current_ip) in stale {
+                let existing: Option<&DnsRecord> = dns_records
+                    .iter()
+                    .find(|record| record.r#type == record_type.as_str() && record.name == entry.name);
+
+                match existing {
+                    Some(record) => {
+                        let mut dns_record: DnsRecord = record.clone();
+                        let previous_ip: String = dns_record.content.clone();
+                        dns_record.content = current_ip.to_string();
+
+                        let outcome: bool = patch_dns_record(&config.api_key, &dns_record)
+                            .context("Patching DNS Record")?
+                            .success;
+                        if outcome {
+                            info!(
+                                "Updated {record_type} Record for {} from {previous_ip} to {current_ip}",
+                                entry.name
+                            );
+                        } else {
+                            warn!("Cloudflare rejected {record_type} Record update for {}", entry.name);
+                        }
+                    }
+                    None => {
+                        let outcome: bool = create_dns_record(
+                            &config.api_key,
+                            &zone.id,
+                            &entry.name,
+                            record_type,
+                            &current_ip.to_string(),
+                            DEFAULT_TTL,
+                        )
+                        .context("Creating DNS Record")?
+                        .success;
+                        if outcome {
+                            info!("Created {record_type} Record for {} with {current_ip}", entry.name);
+                        } else {
+                            warn!("Cloudflare rejected {record_type} Record creation for {}", entry.name);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    *last_seen = current_addresses;
+    Ok(())
+}
+
+/// Builds a [`Config`] from either `--config` or the legacy single-domain CLI
+/// arguments, so the rest of the program only has to handle one shape.
+fn build_config(args: Cli) -> Result<Config> {
+    if let Some(path) = args.config {
+        let file = File::open(&path).with_context(|| format!("Opening config file {}", path.display()))?;
+        let config: Config = serde_json::from_reader(file)
+            .with_context(|| format!("Parsing config file {}", path.display()))?;
+        return Ok(config);
+    }
+
+    let domain: String = args.domain.context("Either DOMAIN or --config must be given")?;
+    let api_key: String = env::var("CLOUDFLARE_API_KEY")?;
     let zone_id: String = get_zones(&api_key)?
         .into_iter()
         .find(|zone| zone.name == domain)
         .map(|zone| zone.id)
         .context("Getting Zone for Domain")?;
 
-    let mut dns_record: DnsRecord = get_dns_records(&api_key, &zone_id)?
-        .into_iter()
-        .find(|record| record.r#type == "A" && record.name == domain)
-        .context("Getting A Record")?;
-    dns_record.content = current_ip.to_string();
-
-    let outcome: bool = patch_dns_record(&api_key, &dns_record)
-        .context("Patching DNS Record")?
-        .success;
-    if outcome {
-        println!("Updated DNS Record from {dns_ip} to {current_ip}");
-    }
+    Ok(Config {
+        api_key,
+        ipv6_interface: args.ipv6_interface,
+        ipv4_providers: default_ipv4_providers(),
+        ipv6_providers: default_ipv6_providers(),
+        zones: vec![ZoneConfig {
+            name: domain.clone(),
+            id: zone_id,
+            email: String::new(),
+            entries: vec![EntryConfig {
+                name: domain,
+                type4: true,
+                type6: true,
+            }],
+        }],
+    })
+}
 
-    Ok(())
+/// Gets the current public IPv4 address, trying each of `providers` in turn.
+fn public_ip(providers: &[String]) -> Result<IpAddr> {
+    fetch_public_ip(providers, RecordType::A)
 }
 
-/// Gets the current public IPv4 address.
+/// Gets the current public IPv6 address.
 ///
-/// GET request is made to <https://api.ipify.org>.
-fn public_ip() -> Result<IpAddr, ureq::Error> {
-    let ip: IpAddr = ureq::get("https://api.ipify.org")
-        .call()?
-        .into_string()?
+/// If `interface` is given, reads the address directly off that local
+/// network interface instead. SLAAC-configured interfaces carry the same
+/// prefix that's routed to them publicly, so no external request is needed.
+/// Otherwise, tries each of `providers` in turn.
+fn public_ipv6(interface: Option<&str>, providers: &[String]) -> Result<IpAddr> {
+    if let Some(interface) = interface {
+        return public_ipv6_from_interface(interface);
+    }
+
+    fetch_public_ip(providers, RecordType::AAAA)
+}
+
+/// Queries `providers` in order, returning the first address that parses and
+/// matches the address family of `record_type`. Only errors once every
+/// provider has failed.
+fn fetch_public_ip(providers: &[String], record_type: RecordType) -> Result<IpAddr> {
+    let mut last_err: Option<anyhow::Error> = None;
+    for provider in providers {
+        match fetch_ip_from(provider, record_type) {
+            Ok(ip) => return Ok(ip),
+            Err(err) => {
+                warn!("IP provider {provider} failed: {err:#}");
+                last_err = Some(err);
+            }
+        }
+    }
+    Err(last_err.unwrap_or_else(|| anyhow::anyhow!("No IP providers configured for {record_type}")))
+}
+
+/// Fetches and parses the body of `provider` as an address matching `record_type`.
+fn fetch_ip_from(provider: &str, record_type: RecordType) -> Result<IpAddr> {
+    let body: String = ureq::get(provider)
+        .call()
+        .with_context(|| format!("Requesting {provider}"))?
+        .into_string()
+        .context("Reading provider response")?;
+    let ip: IpAddr = body
+        .trim()
         .parse()
-        .unwrap();
+        .with_context(|| format!("Parsing response from {provider}"))?;
+
+    let family_matches: bool = match record_type {
+        RecordType::A => ip.is_ipv4(),
+        RecordType::AAAA => ip.is_ipv6(),
+    };
+    if !family_matches {
+        anyhow::bail!("{provider} returned a {ip}, which is not a {record_type} address");
+    }
+
     Ok(ip)
 }
 
-/// Performs a DNS lookup on `domain` and returns first result.
-fn dns_ip(domain: &str) -> Result<IpAddr, anyhow::Error> {
+/// Reads the global IPv6 address assigned to local network interface `interface`.
+///
+/// SLAAC assigns the interface a full address (routable prefix plus host
+/// identifier), so the address is returned as-is: it's the one Cloudflare
+/// needs to route traffic back to this host.
+fn public_ipv6_from_interface(interface: &str) -> Result<IpAddr> {
+    let address: Ipv6Addr = if_addrs::get_if_addrs()
+        .context("Listing network interfaces")?
+        .into_iter()
+        .find_map(|iface| match iface.ip() {
+            IpAddr::V6(v6) if iface.name == interface && is_global_ipv6(v6) => Some(v6),
+            _ => None,
+        })
+        .with_context(|| format!("No global IPv6 Address found on interface {interface}"))?;
+
+    Ok(IpAddr::V6(address))
+}
+
+/// Returns whether `address` is a publicly routable, non-multicast IPv6 address.
+fn is_global_ipv6(address: Ipv6Addr) -> bool {
+    !address.is_loopback()
+        && !address.is_unicast_link_local()
+        && !address.is_unique_local()
+        && !address.is_multicast()
+}
+
+/// Performs a DNS lookup on `domain` and returns the first result matching `record_type`.
+fn dns_ip(domain: &str, record_type: RecordType) -> Result<IpAddr> {
     let ip: IpAddr = format!("{domain}:80")
         .to_socket_addrs()?
-        .next()
-        .context("Getting IPv4 Address For Domain")?
-        .ip();
+        .map(|addr| addr.ip())
+        .find(|ip| match record_type {
+            RecordType::A => ip.is_ipv4(),
+            RecordType::AAAA => ip.is_ipv6(),
+        })
+        .with_context(|| format!("Getting {record_type} Address For Domain"))?;
     Ok(ip)
 }
 
@@ -148,3 +472,36 @@ fn patch_dns_record(
         .into_json()?;
     Ok(response)
 }
+
+/// The TTL Cloudflare treats as "Auto", used by default for records this tool creates.
+const DEFAULT_TTL: isize = 1;
+
+/// Creates a new DNS record named `name` of type `record_type` pointing at `content`,
+/// with time-to-live `ttl`.
+///
+/// Used when no existing record matches the domain/type being synced.
+///
+/// Uses Cloudflare v4 API. See <https://developers.cloudflare.com/api/operations/dns-records-for-a-zone-create-dns-record>.
+fn create_dns_record(
+    api_key: &str,
+    zone_id: &str,
+    name: &str,
+    record_type: RecordType,
+    content: &str,
+    ttl: isize,
+) -> Result<PatchDnsRecordsResponse, ureq::Error> {
+    let auth: &str = &format!("Bearer {api_key}");
+    let url: &str = &format!("https://api.cloudflare.com/client/v4/zones/{zone_id}/dns_records");
+    let response: PatchDnsRecordsResponse = ureq::post(url)
+        .set("Authorization", auth)
+        .set("Content-Type", "application/json")
+        .send_json(ureq::json!({
+            "type": record_type.as_str(),
+            "name": name,
+            "content": content,
+            "ttl": ttl,
+            "proxied": false,
+        }))?
+        .into_json()?;
+    Ok(response)
+}